@@ -1,7 +1,279 @@
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
 use dashmap::DashMap;
+use rand::seq::SliceRandom;
 
 /// A thread-safe, timed key-value store that allows expiration of keys.
 ///
-struct TMap<K, V> {
-    map: DashMap<K, V>,
+/// Values are stored alongside an optional deadline. Expiration is lazy:
+/// a key past its deadline is only removed when it is next looked up via
+/// [`TMap::get`], [`TMap::remove`], [`TMap::expire`], [`TMap::persist`] or
+/// [`TMap::ttl`]. Callers that want keys reclaimed
+/// without being looked up (e.g. a background sweeper) can use
+/// [`TMap::sample_and_expire`].
+pub struct TMap<K, V> {
+    map: DashMap<K, (V, Option<Instant>)>,
+}
+
+// Written by hand instead of `#[derive(Clone)]`: the derive would only
+// require `K: Clone, V: Clone` on this impl, but `DashMap`'s own `Clone`
+// impl additionally requires `K: Eq + Hash`, which the derive doesn't know
+// to add.
+impl<K, V> Clone for TMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for TMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Largest TTL a key may be given. `Instant + Duration` panics on overflow,
+/// and callers pass client-supplied seconds straight through, so any
+/// requested TTL is clamped to this before being added to `Instant::now()`.
+const MAX_TTL: Duration = Duration::from_secs(10 * 365 * 24 * 60 * 60);
+
+impl<K, V> TMap<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self { map: DashMap::new() }
+    }
+
+    /// Inserts `val` for `key` with no expiry, returning the previous value
+    /// (expired or not).
+    pub fn insert(&self, key: K, val: V) -> Option<V> {
+        self.map.insert(key, (val, None)).map(|(v, _)| v)
+    }
+
+    /// Inserts `val` for `key`, expiring after `ttl`. `ttl` is clamped to
+    /// [`MAX_TTL`] so an attacker-supplied duration can't overflow `Instant`.
+    pub fn insert_with_ttl(&self, key: K, val: V, ttl: Duration) -> Option<V> {
+        self.map
+            .insert(key, (val, Some(Instant::now() + ttl.min(MAX_TTL))))
+            .map(|(v, _)| v)
+    }
+
+    /// Returns the value for `key`, lazily evicting it first if its deadline
+    /// has passed.
+    pub fn get(&self, key: &K) -> Option<V> {
+        if self.evict_if_expired(key) {
+            return None;
+        }
+        self.map.get(key).map(|entry| entry.value().0.clone())
+    }
+
+    /// Removes and returns the value for `key`, treating an expired key as
+    /// already absent.
+    pub fn remove(&self, key: &K) -> Option<V> {
+        if self.evict_if_expired(key) {
+            return None;
+        }
+        self.map.remove(key).map(|(_, (v, _))| v)
+    }
+
+    pub fn clear(&self) {
+        self.map.clear();
+    }
+
+    /// Sets `key`'s deadline to `ttl` from now, clamped to [`MAX_TTL`].
+    /// Returns `false` if the key does not exist (or has already expired).
+    pub fn expire(&self, key: &K, ttl: Duration) -> bool {
+        if self.evict_if_expired(key) {
+            return false;
+        }
+        match self.map.get_mut(key) {
+            Some(mut entry) => {
+                entry.value_mut().1 = Some(Instant::now() + ttl.min(MAX_TTL));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Clears `key`'s deadline, if any. Returns `false` if the key does not
+    /// exist.
+    pub fn persist(&self, key: &K) -> bool {
+        if self.evict_if_expired(key) {
+            return false;
+        }
+        match self.map.get_mut(key) {
+            Some(mut entry) => {
+                entry.value_mut().1 = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remaining time to live in seconds: `-2` if the key is missing
+    /// (or expired), `-1` if it has no expiry, otherwise the seconds left.
+    pub fn ttl(&self, key: &K) -> i64 {
+        if self.evict_if_expired(key) {
+            return -2;
+        }
+        match self.map.get(key) {
+            Some(entry) => match entry.value().1 {
+                Some(deadline) => deadline
+                    .saturating_duration_since(Instant::now())
+                    .as_secs() as i64,
+                None => -1,
+            },
+            None => -2,
+        }
+    }
+
+    /// Samples up to `sample_size` keys that carry a TTL and evicts the ones
+    /// past their deadline. Returns `(sampled, expired)`, mirroring the
+    /// active-expiry cycle Redis runs on its own keyspace.
+    ///
+    /// The sample is drawn uniformly at random from the TTL-bearing keys
+    /// rather than taken in `DashMap`'s iteration order, so repeated sweeps
+    /// don't get stuck re-inspecting the same leading subset while expired
+    /// keys further back in that order never get reclaimed.
+    pub fn sample_and_expire(&self, sample_size: usize) -> (usize, usize) {
+        let now = Instant::now();
+        let mut candidates: Vec<K> = self
+            .map
+            .iter()
+            .filter(|entry| entry.value().1.is_some())
+            .map(|entry| entry.key().clone())
+            .collect();
+        candidates.shuffle(&mut rand::thread_rng());
+        candidates.truncate(sample_size);
+
+        let expired = candidates
+            .iter()
+            .filter(|key| self.evict_if_expired_at(key, now))
+            .count();
+
+        (candidates.len(), expired)
+    }
+
+    /// Removes `key` if it has a deadline that has passed, returning whether
+    /// it was (or already had been) evicted.
+    fn evict_if_expired(&self, key: &K) -> bool {
+        self.evict_if_expired_at(key, Instant::now())
+    }
+
+    fn evict_if_expired_at(&self, key: &K, now: Instant) -> bool {
+        let expired = matches!(
+            self.map.get(key).map(|entry| entry.value().1),
+            Some(Some(deadline)) if deadline <= now
+        );
+        if expired {
+            self.map.remove(key);
+        }
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_returns_none_for_missing_key() {
+        let map: TMap<String, String> = TMap::new();
+        assert_eq!(map.get(&"missing".to_string()), None);
+    }
+
+    #[test]
+    fn insert_without_ttl_never_expires() {
+        let map = TMap::new();
+        map.insert("key".to_string(), "val".to_string());
+        assert_eq!(map.get(&"key".to_string()), Some("val".to_string()));
+        assert_eq!(map.ttl(&"key".to_string()), -1);
+    }
+
+    #[test]
+    fn ttl_reports_missing_key_as_minus_two() {
+        let map: TMap<String, String> = TMap::new();
+        assert_eq!(map.ttl(&"missing".to_string()), -2);
+    }
+
+    #[test]
+    fn insert_with_ttl_expires_lazily() {
+        let map = TMap::new();
+        map.insert_with_ttl("key".to_string(), "val".to_string(), Duration::from_millis(1));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(map.get(&"key".to_string()), None);
+        assert_eq!(map.ttl(&"key".to_string()), -2);
+    }
+
+    #[test]
+    fn expire_sets_a_deadline_on_an_existing_key() {
+        let map = TMap::new();
+        map.insert("key".to_string(), "val".to_string());
+        assert!(map.expire(&"key".to_string(), Duration::from_secs(60)));
+        let ttl = map.ttl(&"key".to_string());
+        assert!(ttl > 0 && ttl <= 60);
+    }
+
+    #[test]
+    fn expire_on_missing_key_returns_false() {
+        let map: TMap<String, String> = TMap::new();
+        assert!(!map.expire(&"missing".to_string(), Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn expire_clamps_huge_ttl_instead_of_panicking() {
+        let map = TMap::new();
+        map.insert("key".to_string(), "val".to_string());
+        assert!(map.expire(&"key".to_string(), Duration::MAX));
+        assert!(map.ttl(&"key".to_string()) > 0);
+    }
+
+    #[test]
+    fn persist_clears_a_deadline() {
+        let map = TMap::new();
+        map.insert_with_ttl("key".to_string(), "val".to_string(), Duration::from_secs(60));
+        assert!(map.persist(&"key".to_string()));
+        assert_eq!(map.ttl(&"key".to_string()), -1);
+    }
+
+    #[test]
+    fn remove_treats_expired_key_as_absent() {
+        let map = TMap::new();
+        map.insert_with_ttl("key".to_string(), "val".to_string(), Duration::from_millis(1));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(map.remove(&"key".to_string()), None);
+    }
+
+    #[test]
+    fn sample_and_expire_reclaims_expired_keys() {
+        let map = TMap::new();
+        for i in 0..10 {
+            map.insert_with_ttl(format!("key{i}"), "val".to_string(), Duration::from_millis(1));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+        let (sampled, expired) = map.sample_and_expire(5);
+        assert_eq!(sampled, 5);
+        assert_eq!(expired, 5);
+    }
+
+    #[test]
+    fn sample_and_expire_ignores_keys_without_a_ttl() {
+        let map = TMap::new();
+        map.insert("key".to_string(), "val".to_string());
+        let (sampled, expired) = map.sample_and_expire(10);
+        assert_eq!(sampled, 0);
+        assert_eq!(expired, 0);
+    }
 }
@@ -1,49 +1,388 @@
 use std::fmt::Debug;
-use std::io;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
 
-use dashmap::mapref::one::Ref;
-use dashmap::{DashMap, Map};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
+use async_trait::async_trait;
+use bytes::{Buf, BufMut, BytesMut};
+use dashmap::DashMap;
+use futures::SinkExt;
+use not_redis::TMap;
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tokio_rustls::TlsAcceptor;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{StreamExt, StreamMap};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::WebSocketStream;
+use tokio_util::codec::{Decoder, Encoder, Framed};
 
 const LOCALHOST: &str = "127.0.0.1";
 const DEFAULT_PORT: u16 = 6791;
+/// Port the WebSocket listener binds alongside the raw TCP one.
+const WS_PORT: u16 = 6792;
+
+/// Path to a PEM certificate chain. When this and [`TLS_KEY_ENV`] are both
+/// set, the raw TCP listener accepts TLS connections instead of plaintext
+/// ones; the WebSocket listener stays plaintext.
+const TLS_CERT_ENV: &str = "NOT_REDIS_TLS_CERT";
+/// Path to the PEM private key matching [`TLS_CERT_ENV`].
+const TLS_KEY_ENV: &str = "NOT_REDIS_TLS_KEY";
+/// When set, clients must `AUTH` with this password before anything besides
+/// `PING`/`AUTH` is accepted.
+const AUTH_PASSWORD_ENV: &str = "NOT_REDIS_PASSWORD";
+
+/// How often the active expiry sweep samples the keyspace.
+const SWEEP_INTERVAL: Duration = Duration::from_millis(100);
+/// How many TTL-bearing keys the sweep samples per cycle.
+const SWEEP_SAMPLE_SIZE: usize = 20;
+/// If more than this fraction of a sample was expired, sweep again
+/// immediately instead of waiting for the next tick.
+const SWEEP_REPEAT_THRESHOLD: f64 = 0.25;
+/// Backlog kept for a slow subscriber before `PUBLISH` starts lagging it.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Wire protocol version advertised during the connection handshake.
+const PROTOCOL_VERSION: u8 = 1;
+/// Bit of the handshake's capability byte that advertises LZ4 frame
+/// compression support.
+const CAP_COMPRESSION: u8 = 0b0000_0001;
+/// Capabilities this build supports, ANDed against the peer's to get the
+/// capabilities a connection actually negotiates.
+const SUPPORTED_CAPABILITIES: u8 = CAP_COMPRESSION;
+/// Largest on-wire command frame the codec will accept. Outgoing responses
+/// are not checked against it.
+const MAX_MESSAGE_LEN: usize = 1024;
+
+/// Fixed header exchanged by both sides before the command loop starts, so
+/// client and server agree on a common subset of optional features (e.g.
+/// compression) before any command is sent. Each side advertises its
+/// protocol version; the server reads the peer's version but does not
+/// compare it with its own.
+struct Handshake {
+    version: u8,
+    capabilities: u8,
+}
+
+impl Handshake {
+    const LEN: usize = 2;
+
+    fn ours() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            capabilities: SUPPORTED_CAPABILITIES,
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; Self::LEN] {
+        [self.version, self.capabilities]
+    }
+
+    fn from_bytes(bytes: [u8; Self::LEN]) -> Self {
+        Self {
+            version: bytes[0],
+            capabilities: bytes[1],
+        }
+    }
+}
+
+/// [`tokio_util::codec::Decoder`]/[`Encoder`] for the `length(u32) + utf8
+/// command` frame format, so [`Connection::run`] can drive the socket
+/// through a single [`Framed`] stream/sink instead of hand-rolled reads and
+/// writes. Carries the capabilities negotiated by the handshake so frames
+/// are transparently (de)compressed when both peers support it.
+struct CommandCodec {
+    capabilities: u8,
+    /// Bytes still to be discarded from an oversized frame that was already
+    /// reported as [`MyError::MessageTooLong`], so the connection can resync
+    /// on the next frame instead of re-reading the same header forever.
+    skip_remaining: usize,
+}
+
+impl CommandCodec {
+    fn new(capabilities: u8) -> Self {
+        Self {
+            capabilities,
+            skip_remaining: 0,
+        }
+    }
+}
+
+impl Decoder for CommandCodec {
+    type Item = Command;
+    type Error = MyError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if self.skip_remaining > 0 {
+            let skip = self.skip_remaining.min(src.len());
+            src.advance(skip);
+            self.skip_remaining -= skip;
+            return Ok(None);
+        }
+
+        if src.len() < 4 {
+            return Ok(None);
+        }
+
+        let msg_size = u32::from_be_bytes(src[..4].try_into().unwrap()) as usize;
+        if msg_size > MAX_MESSAGE_LEN {
+            src.advance(4);
+            let skip = msg_size.min(src.len());
+            src.advance(skip);
+            self.skip_remaining = msg_size - skip;
+            return Err(MyError::MessageTooLong);
+        }
+        if src.len() < 4 + msg_size {
+            src.reserve(4 + msg_size - src.len());
+            return Ok(None);
+        }
+
+        src.advance(4);
+        let frame = src.split_to(msg_size);
+
+        let bytes = if self.capabilities & CAP_COMPRESSION != 0 {
+            lz4_flex::decompress_size_prepended(&frame).map_err(|_| MyError::InvalidCommand)?
+        } else {
+            frame.to_vec()
+        };
+        let msg = String::from_utf8(bytes).map_err(|_| MyError::NonUtf8)?;
+
+        Command::try_from(msg.as_str()).map(Some)
+    }
+}
+
+impl Encoder<Response> for CommandCodec {
+    type Error = MyError;
+
+    fn encode(&mut self, resp: Response, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let resp = resp.to_bytes();
+        let payload = if self.capabilities & CAP_COMPRESSION != 0 {
+            lz4_flex::compress_prepend_size(resp.as_bytes())
+        } else {
+            resp.into_bytes()
+        };
+
+        dst.reserve(4 + payload.len());
+        dst.put_u32(payload.len() as u32);
+        dst.extend_from_slice(&payload);
+        Ok(())
+    }
+}
+
+/// Carries a [`Connection`] between a byte stream and the command loop, so
+/// the same `handle_command` dispatch can serve both length-prefixed TCP
+/// frames and one-message-per-command WebSocket frames.
+#[async_trait]
+trait Transport: Send {
+    async fn recv(&mut self) -> Option<Result<Command, MyError>>;
+    async fn send(&mut self, resp: Response) -> Result<(), MyError>;
+}
+
+/// Raw TCP (or TLS-over-TCP) transport: commands are `length(u32) + utf8`
+/// frames decoded by [`CommandCodec`].
+struct TcpTransport<S> {
+    framed: Framed<S, CommandCodec>,
+    /// Whether the last item `framed` yielded was a decode error.
+    errored: bool,
+}
+
+impl<S> TcpTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Exchanges [`Handshake`]s with the peer, then wraps the socket in a
+    /// [`Framed`] using the negotiated capabilities.
+    async fn new(mut socket: S) -> Result<Self, MyError> {
+        let ours = Handshake::ours();
+        socket.write_all(&ours.to_bytes()).await?;
+        socket.flush().await?;
+
+        let mut buf = [0u8; Handshake::LEN];
+        socket.read_exact(&mut buf).await?;
+        let theirs = Handshake::from_bytes(buf);
+        let capabilities = ours.capabilities & theirs.capabilities;
+
+        Ok(Self {
+            framed: Framed::new(socket, CommandCodec::new(capabilities)),
+            errored: false,
+        })
+    }
+}
+
+#[async_trait]
+impl<S> Transport for TcpTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    async fn recv(&mut self) -> Option<Result<Command, MyError>> {
+        let mut item = self.framed.next().await;
+        // After a decode error `Framed` yields one `None` before it resumes
+        // reading; skip it so a recoverable error doesn't end the connection.
+        if self.errored && item.is_none() {
+            item = self.framed.next().await;
+        }
+        self.errored = matches!(item, Some(Err(_)));
+        item
+    }
+
+    async fn send(&mut self, resp: Response) -> Result<(), MyError> {
+        self.framed.send(resp).await
+    }
+}
+
+/// WebSocket transport: each binary message carries exactly one command or
+/// one response, so there's no length prefix to manage, though messages are
+/// still capped at [`MAX_MESSAGE_LEN`] like the TCP side. There is no
+/// handshake phase on this path (browser/NAT-friendly clients can't easily
+/// speak the raw pre-command header), so compression is never negotiated
+/// for WebSocket connections.
+struct WsTransport<S> {
+    ws: WebSocketStream<S>,
+}
+
+impl<S> WsTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    async fn new(socket: S) -> Result<Self, MyError> {
+        let ws = tokio_tungstenite::accept_async(socket)
+            .await
+            .map_err(|e| MyError::WebSocket(e.to_string()))?;
+        Ok(Self { ws })
+    }
+}
+
+#[async_trait]
+impl<S> Transport for WsTransport<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send,
+{
+    async fn recv(&mut self) -> Option<Result<Command, MyError>> {
+        loop {
+            return match self.ws.next().await? {
+                Ok(WsMessage::Binary(bytes)) if bytes.len() > MAX_MESSAGE_LEN => {
+                    Some(Err(MyError::MessageTooLong))
+                }
+                Ok(WsMessage::Binary(bytes)) => Some(
+                    String::from_utf8(bytes)
+                        .map_err(|_| MyError::NonUtf8)
+                        .and_then(|msg| Command::try_from(msg.as_str())),
+                ),
+                Ok(_) => continue,
+                Err(e) => Some(Err(MyError::WebSocket(e.to_string()))),
+            };
+        }
+    }
+
+    async fn send(&mut self, resp: Response) -> Result<(), MyError> {
+        let bytes = resp.to_bytes().into_bytes();
+        self.ws
+            .send(WsMessage::Binary(bytes))
+            .await
+            .map_err(|e| MyError::WebSocket(e.to_string()))
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), MyError> {
-    let listener = TcpListener::bind((LOCALHOST, DEFAULT_PORT)).await?;
-    ConnectionManager::new(listener).run().await
+    let tcp_listener = TcpListener::bind((LOCALHOST, DEFAULT_PORT)).await?;
+    let ws_listener = TcpListener::bind((LOCALHOST, WS_PORT)).await?;
+    let tls_acceptor = load_tls_acceptor()?;
+    let password: Option<Arc<str>> = std::env::var(AUTH_PASSWORD_ENV).ok().map(Arc::from);
+    ConnectionManager::new(tcp_listener, ws_listener, tls_acceptor, password)
+        .run()
+        .await
+}
+
+/// Builds a [`TlsAcceptor`] from the cert/key pair named by [`TLS_CERT_ENV`]
+/// and [`TLS_KEY_ENV`], or `None` if TLS isn't configured.
+fn load_tls_acceptor() -> Result<Option<TlsAcceptor>, MyError> {
+    let (cert_path, key_path) = match (std::env::var(TLS_CERT_ENV), std::env::var(TLS_KEY_ENV)) {
+        (Ok(cert), Ok(key)) => (cert, key),
+        _ => return Ok(None),
+    };
+
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert_path)?))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| MyError::Tls(e.to_string()))?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(key_path)?))
+        .map_err(|e| MyError::Tls(e.to_string()))?
+        .ok_or_else(|| MyError::Tls("no private key found".to_string()))?;
+
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| MyError::Tls(e.to_string()))?;
+
+    Ok(Some(TlsAcceptor::from(Arc::new(config))))
 }
 
-pub struct Connection {
-    socket: TcpStream,
-    store: DashMap<String, String>,
-    buffer: [u8; Self::BUFLEN],
+struct Connection<T> {
+    transport: T,
+    store: Arc<TMap<String, String>>,
+    channels: Arc<DashMap<String, broadcast::Sender<String>>>,
+    subscriptions: StreamMap<String, BroadcastStream<String>>,
+    password: Option<Arc<str>>,
+    authenticated: bool,
 }
 
 struct ConnectionManager {
-    store: DashMap<String, String>,
-    listener: TcpListener,
+    store: Arc<TMap<String, String>>,
+    channels: Arc<DashMap<String, broadcast::Sender<String>>>,
+    tcp_listener: TcpListener,
+    ws_listener: TcpListener,
+    tls_acceptor: Option<TlsAcceptor>,
+    password: Option<Arc<str>>,
 }
 
 impl ConnectionManager {
-    fn new(listener: TcpListener) -> Self {
+    fn new(
+        tcp_listener: TcpListener,
+        ws_listener: TcpListener,
+        tls_acceptor: Option<TlsAcceptor>,
+        password: Option<Arc<str>>,
+    ) -> Self {
         Self {
-            store: DashMap::new(),
-            listener,
+            store: Arc::new(TMap::new()),
+            channels: Arc::new(DashMap::new()),
+            tcp_listener,
+            ws_listener,
+            tls_acceptor,
+            password,
         }
     }
 
     async fn run(self) -> Result<(), MyError> {
+        tokio::spawn(Self::sweep_expired(self.store.clone()));
+
+        tokio::try_join!(self.run_tcp(), self.run_ws())?;
+        Ok(())
+    }
+
+    /// Accepts raw TCP (optionally TLS-wrapped) connections, negotiating the
+    /// [`Handshake`] before handing each socket to a [`Connection`].
+    async fn run_tcp(&self) -> Result<(), MyError> {
         loop {
-            match self.listener.accept().await {
+            match self.tcp_listener.accept().await {
                 Ok((socket, addr)) => {
                     println!("Connection on {addr:?}");
                     let store = self.store.clone();
+                    let channels = self.channels.clone();
+                    let password = self.password.clone();
+                    let tls_acceptor = self.tls_acceptor.clone();
                     tokio::spawn(async move {
-                        let conn = Connection::new(socket, store.clone());
-                        if let Err(e) = conn.run().await {
-                            println!("Error: {e:?}");
+                        match tls_acceptor {
+                            Some(acceptor) => match acceptor.accept(socket).await {
+                                Ok(socket) => {
+                                    Self::serve_tcp(socket, addr, store, channels, password).await
+                                }
+                                Err(e) => println!("TLS handshake with {addr:?} failed: {e:?}"),
+                            },
+                            None => Self::serve_tcp(socket, addr, store, channels, password).await,
                         }
                     });
                 }
@@ -53,86 +392,211 @@ impl ConnectionManager {
             }
         }
     }
-}
-
-impl Connection {
-    const BUFLEN: usize = 1024;
 
-    pub fn new(socket: TcpStream, store: DashMap<String, String>) -> Self {
-        Self {
-            socket,
-            store,
-            buffer: [0; Self::BUFLEN],
+    /// Builds a [`TcpTransport`] over `socket` (plaintext or already
+    /// TLS-wrapped) and drives the resulting [`Connection`] to completion.
+    /// Shared by both branches of [`Self::run_tcp`] so they don't drift.
+    async fn serve_tcp<S>(
+        socket: S,
+        addr: SocketAddr,
+        store: Arc<TMap<String, String>>,
+        channels: Arc<DashMap<String, broadcast::Sender<String>>>,
+        password: Option<Arc<str>>,
+    ) where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        match TcpTransport::new(socket).await {
+            Ok(transport) => {
+                let conn = Connection::new(transport, store, channels, password);
+                if let Err(e) = conn.run().await {
+                    println!("Error: {e:?}");
+                }
+            }
+            Err(e) => println!("Handshake with {addr:?} failed: {e:?}"),
         }
     }
 
-    pub async fn run(mut self) -> Result<(), MyError> {
+    /// Accepts WebSocket connections on [`WS_PORT`]. These skip the raw
+    /// handshake entirely — the WebSocket upgrade itself is the handshake.
+    async fn run_ws(&self) -> Result<(), MyError> {
         loop {
-            println!("in run loop");
-            match self.read_command().await {
-                Ok(cmd) => {
-                    let resp = self.handle_command(cmd);
-                    self.reply(resp).await?;
+            match self.ws_listener.accept().await {
+                Ok((socket, addr)) => {
+                    println!("WebSocket connection on {addr:?}");
+                    let store = self.store.clone();
+                    let channels = self.channels.clone();
+                    let password = self.password.clone();
+                    tokio::spawn(async move {
+                        match WsTransport::new(socket).await {
+                            Ok(transport) => {
+                                let conn = Connection::new(transport, store, channels, password);
+                                if let Err(e) = conn.run().await {
+                                    println!("Error: {e:?}");
+                                }
+                            }
+                            Err(e) => println!("WebSocket upgrade with {addr:?} failed: {e:?}"),
+                        }
+                    });
                 }
-                Err(e) => match e {
-                    MyError::Io(_) => todo!(),
-                    MyError::InvalidCommand => todo!(),
-                    MyError::NotEnoughArgs => todo!(),
-                    MyError::NoCommand => todo!(),
-                    MyError::MessageTooLong => todo!(),
-                    MyError::Disconnected => todo!(),
-                    MyError::ConnectClosed => todo!(),
-                    MyError::NonUtf8 => todo!(),
-                },
-            }
-            match self.socket.read(&mut self.buffer).await {
-                Ok(0) => break,
-                Ok(n) => {
-                    // handle the input
-                    let s = std::str::from_utf8(&self.buffer).unwrap();
-                    println!("Received: `{s}` of len({n})");
+                Err(e) => {
+                    println!("Error: {e:?}");
                 }
-                Err(e) => return Err(MyError::from(e)),
             }
         }
-        Ok(())
     }
 
-    async fn reply(&mut self, resp: Response) -> Result<(), MyError> {
-        let resp = resp.to_bytes();
-        self.socket.write_u32(resp.len() as u32).await?;
-        self.socket.write_all(resp.as_bytes()).await?;
-        self.socket.flush().await?;
-        Ok(())
+    /// Background active-expiry cycle, modeled on Redis: repeatedly sample a
+    /// handful of keys that carry a TTL and evict the expired ones, so the
+    /// keyspace doesn't grow unbounded even if nobody ever reads an expired
+    /// key back. If more than [`SWEEP_REPEAT_THRESHOLD`] of a sample comes back
+/// expired there's likely more
+    /// to reclaim, so it samples again immediately rather than waiting out
+    /// the full interval.
+    async fn sweep_expired(store: Arc<TMap<String, String>>) {
+        loop {
+            loop {
+                let (sampled, expired) = store.sample_and_expire(SWEEP_SAMPLE_SIZE);
+                if sampled == 0 || (expired as f64) <= (sampled as f64) * SWEEP_REPEAT_THRESHOLD {
+                    break;
+                }
+            }
+            tokio::time::sleep(SWEEP_INTERVAL).await;
+        }
     }
+}
 
-    async fn read_command(&mut self) -> Result<Command, MyError> {
-        let msg_size = self.socket.read_u32().await? as usize;
-        if msg_size > Self::BUFLEN {
-            return Err(MyError::MessageTooLong);
+impl<T> Connection<T>
+where
+    T: Transport,
+{
+    fn new(
+        transport: T,
+        store: Arc<TMap<String, String>>,
+        channels: Arc<DashMap<String, broadcast::Sender<String>>>,
+        password: Option<Arc<str>>,
+    ) -> Self {
+        Self {
+            transport,
+            store,
+            channels,
+            subscriptions: StreamMap::new(),
+            password,
+            authenticated: false,
         }
+    }
 
-        let buf = &mut self.buffer[..msg_size];
-        self.socket.read_exact(buf).await?;
-
-        let msg = dbg!(String::from_utf8(buf.to_vec()).map_err(|_| MyError::NonUtf8)?);
-
-        Command::try_from(msg.as_str())
+    async fn run(mut self) -> Result<(), MyError> {
+        loop {
+            tokio::select! {
+                cmd = self.transport.recv() => {
+                    match cmd {
+                        Some(Ok(cmd)) => {
+                            let resp = match self.handle_command(cmd) {
+                                Ok(resp) => resp,
+                                Err(e) => Response::Error(e.message()),
+                            };
+                            self.transport.send(resp).await?;
+                        }
+                        Some(Err(e)) => match e {
+                            MyError::Io(_)
+                            | MyError::Disconnected
+                            | MyError::ConnectClosed
+                            | MyError::WebSocket(_) => {
+                                return Ok(());
+                            }
+                            MyError::InvalidCommand
+                            | MyError::NotEnoughArgs
+                            | MyError::NoCommand
+                            | MyError::NonUtf8
+                            | MyError::MessageTooLong
+                            | MyError::Unauthorized => {
+                                self.transport.send(Response::Error(e.message())).await?;
+                            }
+                            MyError::Tls(_) => return Err(e),
+                        },
+                        // The peer closed the connection.
+                        None => return Ok(()),
+                    }
+                }
+                // Only fires once SUBSCRIBE has added at least one channel;
+                // an empty StreamMap resolves to `None` immediately, which
+                // the `Some(..) = ..` pattern disables for this iteration.
+                Some((channel, msg)) = self.subscriptions.next() => {
+                    if let Ok(payload) = msg {
+                        self.transport.send(Response::Message(channel, payload)).await?;
+                    }
+                }
+            }
+        }
     }
 
-    fn handle_command(&mut self, cmd: Command) -> Response {
-        let own = |op: Option<Ref<'_, _, String, _>>| op.map(|v| v.to_string());
+    fn handle_command(&mut self, cmd: Command) -> Result<Response, MyError> {
+        let requires_auth = !matches!(cmd, Command::Ping | Command::Auth(_));
+        if requires_auth && !self.is_authorized() {
+            return Err(MyError::Unauthorized);
+        }
 
-        match cmd {
+        Ok(match cmd {
             Command::Ping => Response::Pong,
             Command::Echo(v) => Response::Echo(v),
-            Command::Get(key) => Response::Get(own(self.store.get(&key))),
+            Command::Get(key) => Response::Get(self.store.get(&key)),
             Command::Set(key, val) => Response::Set(self.store.insert(key, val)),
-            Command::Del(val) => Response::Del(self.store.remove(&val).map(|(_, v)| v)),
+            Command::Del(val) => Response::Del(self.store.remove(&val)),
             Command::Clear => {
                 self.store.clear();
                 Response::Clear
             }
+            Command::Setex(key, secs, val) => Response::Setex(
+                self.store
+                    .insert_with_ttl(key, val, Duration::from_secs(secs)),
+            ),
+            Command::Expire(key, secs) => {
+                Response::Expire(self.store.expire(&key, Duration::from_secs(secs)))
+            }
+            Command::Ttl(key) => Response::Ttl(self.store.ttl(&key)),
+            Command::Persist(key) => Response::Persist(self.store.persist(&key)),
+            Command::Subscribe(channel) => {
+                let sender = self
+                    .channels
+                    .entry(channel.clone())
+                    .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+                    .clone();
+                self.subscriptions
+                    .insert(channel.clone(), BroadcastStream::new(sender.subscribe()));
+                Response::Subscribed(channel)
+            }
+            Command::Unsubscribe(channel) => {
+                self.subscriptions.remove(&channel);
+                Response::Unsubscribed(channel)
+            }
+            Command::Publish(channel, msg) => {
+                let receivers = self
+                    .channels
+                    .get(&channel)
+                    .and_then(|sender| sender.send(msg).ok())
+                    .unwrap_or(0);
+                Response::Published(receivers)
+            }
+            Command::Auth(attempt) => self.auth(&attempt),
+        })
+    }
+
+    /// Whether this connection may run commands other than `PING`/`AUTH`:
+    /// always true when no password is configured, otherwise only after a
+    /// successful `AUTH`.
+    fn is_authorized(&self) -> bool {
+        self.password.is_none() || self.authenticated
+    }
+
+    /// Checks `attempt` against the configured password in constant time, so
+    /// a client can't learn how many leading bytes matched from timing.
+    fn auth(&mut self, attempt: &str) -> Response {
+        match &self.password {
+            Some(password) => {
+                self.authenticated = attempt.as_bytes().ct_eq(password.as_bytes()).into();
+                Response::Auth(self.authenticated)
+            }
+            None => Response::Auth(true),
         }
     }
 }
@@ -144,6 +608,16 @@ enum Response {
     Set(Option<String>),
     Del(Option<String>),
     Clear,
+    Setex(Option<String>),
+    Expire(bool),
+    Ttl(i64),
+    Persist(bool),
+    Subscribed(String),
+    Unsubscribed(String),
+    Published(usize),
+    Message(String, String),
+    Error(&'static str),
+    Auth(bool),
 }
 
 enum Command {
@@ -153,6 +627,14 @@ enum Command {
     Set(String, String),
     Del(String),
     Clear,
+    Setex(String, u64, String),
+    Expire(String, u64),
+    Ttl(String),
+    Persist(String),
+    Subscribe(String),
+    Unsubscribe(String),
+    Publish(String, String),
+    Auth(String),
 }
 
 impl Response {
@@ -173,30 +655,62 @@ impl Response {
                 None => String::from("DEL (nil)"),
             },
             Response::Clear => String::from("CLR"),
+            Response::Setex(v) => match v {
+                Some(v) => format!("SETEX {}", v),
+                None => String::from("SETEX (nil)"),
+            },
+            Response::Expire(ok) => format!("EXPIRE {}", *ok as u8),
+            Response::Ttl(secs) => format!("TTL {}", secs),
+            Response::Persist(ok) => format!("PERSIST {}", *ok as u8),
+            Response::Subscribed(channel) => format!("SUBSCRIBE {}", channel),
+            Response::Unsubscribed(channel) => format!("UNSUBSCRIBE {}", channel),
+            Response::Published(receivers) => format!("PUBLISH {}", receivers),
+            Response::Message(channel, msg) => format!("MESSAGE {} {}", channel, msg),
+            Response::Error(msg) => format!("ERR {}", msg),
+            Response::Auth(ok) => format!("AUTH {}", *ok as u8),
         }
     }
 }
 
+/// Pulls the next space-separated token off `split`, as a `String`.
+fn next_arg(split: &mut std::str::Split<char>) -> Result<String, MyError> {
+    split.next().map(str::to_string).ok_or(MyError::NotEnoughArgs)
+}
+
+/// Pulls the next space-separated token off `split`, parsed as a `u64`.
+fn next_arg_u64(split: &mut std::str::Split<char>) -> Result<u64, MyError> {
+    next_arg(split)?.parse().map_err(|_| MyError::InvalidCommand)
+}
+
 impl TryFrom<&str> for Command {
     type Error = MyError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        println!("parsing: `{value}`");
         let mut split = value.split(' ');
-
         let cmd = split.next();
-        let mut next_arg = || match split.next() {
-            Some(v) => Ok(v.to_string()),
-            None => Err(Self::Error::NotEnoughArgs),
-        };
 
         match cmd {
             Some("PING") => Ok(Self::Ping),
-            Some("ECHO") => Ok(Self::Echo(next_arg()?)),
-            Some("GET") => Ok(Self::Get(next_arg()?)),
-            Some("SET") => Ok(Self::Set(next_arg()?, next_arg()?)),
-            Some("DEL") => Ok(Self::Del(next_arg()?)),
+            Some("ECHO") => Ok(Self::Echo(next_arg(&mut split)?)),
+            Some("GET") => Ok(Self::Get(next_arg(&mut split)?)),
+            Some("SET") => Ok(Self::Set(next_arg(&mut split)?, next_arg(&mut split)?)),
+            Some("DEL") => Ok(Self::Del(next_arg(&mut split)?)),
             Some("CLR") => Ok(Self::Clear),
+            Some("SETEX") => {
+                let key = next_arg(&mut split)?;
+                let secs = next_arg_u64(&mut split)?;
+                Ok(Self::Setex(key, secs, next_arg(&mut split)?))
+            }
+            Some("EXPIRE") => {
+                let key = next_arg(&mut split)?;
+                Ok(Self::Expire(key, next_arg_u64(&mut split)?))
+            }
+            Some("TTL") => Ok(Self::Ttl(next_arg(&mut split)?)),
+            Some("PERSIST") => Ok(Self::Persist(next_arg(&mut split)?)),
+            Some("SUBSCRIBE") => Ok(Self::Subscribe(next_arg(&mut split)?)),
+            Some("UNSUBSCRIBE") => Ok(Self::Unsubscribe(next_arg(&mut split)?)),
+            Some("PUBLISH") => Ok(Self::Publish(next_arg(&mut split)?, next_arg(&mut split)?)),
+            Some("AUTH") => Ok(Self::Auth(next_arg(&mut split)?)),
             Some(_) => Err(Self::Error::InvalidCommand),
             None => Err(Self::Error::NoCommand),
         }
@@ -213,6 +727,9 @@ pub enum MyError {
     Disconnected,
     ConnectClosed,
     NonUtf8,
+    Tls(String),
+    Unauthorized,
+    WebSocket(String),
 }
 
 impl From<io::Error> for MyError {
@@ -220,3 +737,23 @@ impl From<io::Error> for MyError {
         Self::Io(e)
     }
 }
+
+impl MyError {
+    /// Short message sent back to the client for the variants that don't
+    /// end the connection.
+    fn message(&self) -> &'static str {
+        match self {
+            MyError::InvalidCommand => "invalid command",
+            MyError::NotEnoughArgs => "not enough arguments",
+            MyError::NoCommand => "no command",
+            MyError::NonUtf8 => "command was not valid utf-8",
+            MyError::MessageTooLong => "message too long",
+            MyError::Unauthorized => "unauthorized",
+            MyError::Io(_)
+            | MyError::Disconnected
+            | MyError::ConnectClosed
+            | MyError::Tls(_)
+            | MyError::WebSocket(_) => "connection error",
+        }
+    }
+}